@@ -7,6 +7,7 @@ use num_cpus;
 
 mod jobs;
 mod man;
+mod rlimit;
 
 use std::fs;
 
@@ -19,6 +20,10 @@ pub enum ParseErr {
     JobsNaN(String),
     /// No value was supplied for '--jobs'
     JobsNoValue,
+    /// The value supplied for `--buffer-limit` is not a number.
+    BufferLimitNaN(String),
+    /// No value was supplied for `--buffer-limit`
+    BufferLimitNoValue,
     /// The argument supplied is not a valid argument.
     InvalidArgument(String),
     /// No arguments were given to the program.
@@ -41,6 +46,12 @@ impl ParseErr {
             ParseErr::JobsNoValue => {
                 let _ = stderr.write(b"no jobs parameter was defined.\n");
             },
+            ParseErr::BufferLimitNaN(value) => {
+                let _ = write!(&mut stderr, "buffer-limit parameter, '{}', is not a number.\n", value);
+            },
+            ParseErr::BufferLimitNoValue => {
+                let _ = stderr.write(b"no buffer-limit parameter was defined.\n");
+            },
             ParseErr::InvalidArgument(argument) => {
                 let _ = write!(&mut stderr, "invalid argument: {}\n", argument);
             },
@@ -67,7 +78,10 @@ pub struct Flags {
     pub inputs_are_commands: bool,
     pub uses_shell:          bool,
     pub quiet:               bool,
-    pub verbose:             bool
+    pub verbose:             bool,
+    /// When set, jobs are spawned with piped stdout/stderr that are forwarded straight from
+    /// memory instead of being staged in per-job temp files on disk.
+    pub pipe:                bool
 }
 
 impl Flags {
@@ -78,6 +92,7 @@ impl Flags {
             quiet: false,
             verbose: false,
             inputs_are_commands: false,
+            pipe: false,
         }
     }
 }
@@ -85,11 +100,13 @@ impl Flags {
 /// `Args` is a collection of critical options and arguments that were collected at
 /// startup of the application.
 pub struct Args {
-    pub flags:     Flags,
-    pub ncores:    usize,
-    pub arguments: Vec<Token>,
-    pub ninputs:   usize,
-    pub inputs:    Vec<String>
+    pub flags:        Flags,
+    pub ncores:       usize,
+    pub arguments:    Vec<Token>,
+    pub ninputs:      usize,
+    pub inputs:       Vec<String>,
+    /// Overrides the out-of-order completion buffer's high-water mark (see `--buffer-limit`).
+    pub buffer_limit: Option<usize>
 }
 
 impl Args {
@@ -99,11 +116,15 @@ impl Args {
             flags: Flags::new(),
             arguments: Vec::new(),
             ninputs: 0,
-            inputs: Vec::new()
+            inputs: Vec::new(),
+            buffer_limit: None
         }
     }
 
     pub fn parse(&mut self) -> Result<(), ParseErr> {
+        // See `rlimit` for why this has to happen before any jobs start.
+        rlimit::raise_file_limit();
+
         let mut raw_args = env::args().skip(1).peekable();
         let mut comm = String::with_capacity(128);
         let mut lists: Vec<Vec<String>>= Vec::new();
@@ -155,6 +176,7 @@ impl Args {
                                         },
                                         'n' => self.flags.uses_shell = false,
                                         'u' => self.flags.grouped = false,
+                                        'p' => self.flags.pipe = true,
                                         'q' => self.flags.quiet = true,
                                         'v' => self.flags.verbose = true,
                                         _ => {
@@ -175,6 +197,12 @@ impl Args {
                                     },
                                     "ungroup" => self.flags.grouped = false,
                                     "no-shell" => self.flags.uses_shell = false,
+                                    "pipe" => self.flags.pipe = true,
+                                    "buffer-limit" => {
+                                        let val = &try!(raw_args.next().ok_or(ParseErr::BufferLimitNoValue));
+                                        self.buffer_limit = Some(try!(val.parse::<usize>()
+                                            .map_err(|_| ParseErr::BufferLimitNaN(val.clone()))));
+                                    },
                                     "num-cpu-cores" => {
                                         println!("{}", num_cpus::get());
                                         exit(0);
@@ -0,0 +1,97 @@
+//! Raises the soft `RLIMIT_NOFILE` before any jobs start.
+//!
+//! `receive_messages` opens a per-job stdout and stderr file while `jobs` simultaneously spawns
+//! many children, so a large `--jobs` value can exhaust the process's soft file descriptor limit
+//! and surface as spurious I/O errors. This module raises the soft limit to the hard limit on
+//! Unix platforms so that high job counts work without the user manually running `ulimit -n`.
+
+#[cfg(unix)]
+mod unix {
+    use libc::{self, rlimit, RLIMIT_NOFILE};
+    use std::mem;
+
+    /// Raises the soft `RLIMIT_NOFILE` to the hard limit, clamping to the platform's real
+    /// ceiling where `rlim_max` reports more than the kernel will accept.
+    pub fn raise_file_limit() {
+        unsafe {
+            let mut limit: rlimit = mem::zeroed();
+            if libc::getrlimit(RLIMIT_NOFILE, &mut limit) != 0 {
+                return;
+            }
+
+            let target = clamp_to_ceiling(limit.rlim_max, macos_ceiling());
+
+            if target <= limit.rlim_cur {
+                return;
+            }
+
+            limit.rlim_cur = target;
+            let _ = libc::setrlimit(RLIMIT_NOFILE, &limit);
+        }
+    }
+
+    // Darwin rejects a soft limit above `kern.maxfilesperproc` even when it is below
+    // `rlim_max`, so the requested limit must be clamped to that sysctl (and to `OPEN_MAX`)
+    // before calling `setrlimit`.
+    #[cfg(target_os = "macos")]
+    fn macos_ceiling() -> Option<libc::rlim_t> {
+        let mut value: libc::c_int = 0;
+        let mut size = mem::size_of::<libc::c_int>();
+        let name = b"kern.maxfilesperproc\0";
+
+        let result = unsafe {
+            libc::sysctlbyname(
+                name.as_ptr() as *const libc::c_char,
+                &mut value as *mut _ as *mut libc::c_void,
+                &mut size,
+                ::std::ptr::null_mut(),
+                0
+            )
+        };
+
+        if result != 0 || value <= 0 {
+            None
+        } else {
+            Some((value as libc::rlim_t).min(libc::OPEN_MAX as libc::rlim_t))
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn macos_ceiling() -> Option<libc::rlim_t> { None }
+
+    /// Clamps `target` to `ceiling`, if one was reported. Pulled out of `raise_file_limit` so the
+    /// arithmetic can be exercised without a real `sysctlbyname`/`getrlimit` call.
+    fn clamp_to_ceiling(target: libc::rlim_t, ceiling: Option<libc::rlim_t>) -> libc::rlim_t {
+        match ceiling {
+            Some(ceiling) => target.min(ceiling),
+            None => target
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::clamp_to_ceiling;
+
+        #[test]
+        fn clamp_to_ceiling_leaves_target_below_ceiling_untouched() {
+            assert_eq!(clamp_to_ceiling(256, Some(1024)), 256);
+        }
+
+        #[test]
+        fn clamp_to_ceiling_caps_target_above_ceiling() {
+            assert_eq!(clamp_to_ceiling(4096, Some(1024)), 1024);
+        }
+
+        #[test]
+        fn clamp_to_ceiling_is_a_no_op_without_a_ceiling() {
+            assert_eq!(clamp_to_ceiling(4096, None), 4096);
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use self::unix::raise_file_limit;
+
+/// No-op on non-Unix platforms, which have no equivalent of a per-process file descriptor limit.
+#[cfg(not(unix))]
+pub fn raise_file_limit() {}
@@ -0,0 +1,189 @@
+//! Spawns one child process per input and reports its outcome back to the ordering thread.
+//!
+//! Output is staged on disk and tailed by `receive_messages` by default. Under `--pipe`
+//! (`args.flags.pipe`), a job's stdout/stderr are instead drained straight into memory by a
+//! `StreamForwarder` and reported as `State::Piped`; under `--ungroup` that data is written
+//! through to the real stdout/stderr as soon as it's drained instead of waiting for the
+//! ordering thread to emit it in order.
+
+use std::io::{self, Write};
+use std::fs::File;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::Sender;
+
+use arguments::{Args, Flags};
+use filepaths;
+use super::pipe::disk::State;
+use super::pipe::stream::StreamForwarder;
+use super::receive::Backpressure;
+
+/// How long a dispatching thread sleeps while waiting for a free job slot or for the ordering
+/// thread's buffer to drain below its low-water mark.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// How long a `--pipe` job's forwarder sleeps between drains of a still-running child.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Spawns one job per input, honoring `args.ncores` concurrency and pausing whenever
+/// `backpressure` reports that the ordering thread's out-of-order buffer is over its
+/// high-water mark.
+pub fn dispatch(args: &Args, tx: Sender<State>, backpressure: Arc<Backpressure>) {
+    let active = Arc::new(AtomicUsize::new(0));
+
+    for (id, input) in args.inputs.iter().cloned().enumerate() {
+        while backpressure.is_throttled() {
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        while active.load(Ordering::Acquire) >= args.ncores {
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        active.fetch_add(1, Ordering::Acquire);
+
+        let tx = tx.clone();
+        let active = active.clone();
+        let flags = args.flags.clone();
+
+        thread::spawn(move || {
+            run_job(id, &input, &flags, &tx);
+            active.fetch_sub(1, Ordering::Release);
+        });
+    }
+}
+
+/// Builds and runs a single job, sending its outcome over `tx`.
+fn run_job(id: usize, input: &str, flags: &Flags, tx: &Sender<State>) {
+    let mut command = build_command(input, flags.uses_shell);
+
+    if flags.pipe {
+        run_piped(id, input, &mut command, flags, tx);
+    } else {
+        run_on_disk(id, input, &mut command, tx);
+    }
+}
+
+fn build_command(input: &str, uses_shell: bool) -> Command {
+    if uses_shell {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(input);
+        command
+    } else {
+        let mut parts = input.split_whitespace();
+        let mut command = Command::new(parts.next().unwrap_or(""));
+        command.args(parts);
+        command
+    }
+}
+
+/// The default disk-backed path: the child's stdout/stderr are redirected to the per-job temp
+/// files that `receive_messages` tails.
+fn run_on_disk(id: usize, input: &str, command: &mut Command, tx: &Sender<State>) {
+    let (_, stdout_path, stderr_path) = filepaths::new_job(id);
+
+    let stdout_file = match File::create(&stdout_path) {
+        Ok(file) => file,
+        Err(why) => {
+            let _ = tx.send(State::Error(id, format!("unable to create job's stdout file: {}\n", why)));
+            return;
+        }
+    };
+    let stderr_file = match File::create(&stderr_path) {
+        Ok(file) => file,
+        Err(why) => {
+            let _ = tx.send(State::Error(id, format!("unable to create job's stderr file: {}\n", why)));
+            return;
+        }
+    };
+
+    command.stdout(Stdio::from(stdout_file)).stderr(Stdio::from(stderr_file));
+
+    match command.status() {
+        Ok(status) if status.success() => { let _ = tx.send(State::Completed(id, input.to_owned())); },
+        Ok(status) => { let _ = tx.send(State::Error(id, format!("job failed with {}\n", status))); },
+        Err(why) => { let _ = tx.send(State::Error(id, format!("unable to execute job: {}\n", why))); }
+    }
+}
+
+/// The diskless `--pipe` path: the child's stdout/stderr are drained straight into memory and
+/// reported as `State::Piped`. Under `--ungroup` the drained bytes are written through to the
+/// real stdout/stderr immediately, and `Piped` carries empty buffers so the ordering thread only
+/// has to update its bookkeeping.
+fn run_piped(id: usize, input: &str, command: &mut Command, flags: &Flags, tx: &Sender<State>) {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(why) => {
+            let _ = tx.send(State::Error(id, format!("unable to execute job: {}\n", why)));
+            return;
+        }
+    };
+
+    let mut forwarder = match take_pipes(&mut child).and_then(|(out, err)| StreamForwarder::new(out, err)) {
+        Ok(forwarder) => forwarder,
+        Err(why) => {
+            let _ = tx.send(State::Error(id, format!("unable to set up pipe: {}\n", why)));
+            return;
+        }
+    };
+
+    loop {
+        forwarder.drain();
+
+        if !flags.grouped {
+            write_through(&mut forwarder);
+        }
+
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) => thread::sleep(DRAIN_INTERVAL),
+            Err(_) => break
+        }
+    }
+
+    // One final drain in case the child exited with data still sitting in the pipe buffer.
+    forwarder.drain();
+    if !flags.grouped {
+        write_through(&mut forwarder);
+    }
+
+    match child.wait() {
+        Ok(status) if status.success() => {
+            let _ = tx.send(State::Piped(id, input.to_owned(), forwarder.stdout_buffer, forwarder.stderr_buffer));
+        },
+        Ok(status) => {
+            let _ = tx.send(State::Error(id, format!("job failed with {}\n", status)));
+        },
+        Err(why) => {
+            let _ = tx.send(State::Error(id, format!("unable to execute job: {}\n", why)));
+        }
+    }
+}
+
+/// Writes whatever the forwarder has accumulated straight to the real stdout/stderr and clears
+/// the accumulators, used under `--ungroup` where output isn't held for in-order emission.
+fn write_through(forwarder: &mut StreamForwarder) {
+    if !forwarder.stdout_buffer.is_empty() {
+        let stdout = io::stdout();
+        let _ = stdout.lock().write_all(&forwarder.stdout_buffer);
+        forwarder.stdout_buffer.clear();
+    }
+
+    if !forwarder.stderr_buffer.is_empty() {
+        let stderr = io::stderr();
+        let _ = stderr.lock().write_all(&forwarder.stderr_buffer);
+        forwarder.stderr_buffer.clear();
+    }
+}
+
+fn take_pipes(child: &mut Child) -> io::Result<(::std::process::ChildStdout, ::std::process::ChildStderr)> {
+    let stdout = try!(child.stdout.take().ok_or_else(|| io::Error::new(io::ErrorKind::Other, "missing child stdout")));
+    let stderr = try!(child.stderr.take().ok_or_else(|| io::Error::new(io::ErrorKind::Other, "missing child stderr")));
+    Ok((stdout, stderr))
+}
@@ -0,0 +1,5 @@
+//! Spawns jobs and drives their output back to the user in the order they were given.
+
+pub mod jobs;
+pub mod pipe;
+pub mod receive;
@@ -0,0 +1,15 @@
+//! The message a finished job reports back to the ordering thread.
+
+/// Reported by a finished job to `receive_messages`.
+pub enum State {
+    /// The job at `usize` completed; its output was staged on disk and `String` is the line to
+    /// append to the processed file. `receive_messages` tails the job's temp files to recover
+    /// the output.
+    Completed(usize, String),
+    /// The job at `usize` failed; `String` is the already-formatted error message.
+    Error(usize, String),
+    /// The job at `usize` completed in `--pipe` mode; `String` is the line to append to the
+    /// processed file, and the two `Vec<u8>` are its stdout/stderr, already collected in memory
+    /// by a `StreamForwarder` rather than staged on disk.
+    Piped(usize, String, Vec<u8>, Vec<u8>)
+}
@@ -0,0 +1,5 @@
+//! The message finished jobs report (`disk`) and the in-memory forwarder behind the diskless
+//! `--pipe` path (`stream`).
+
+pub mod disk;
+pub mod stream;
@@ -0,0 +1,163 @@
+//! Diskless output forwarding for `--pipe` mode.
+//!
+//! Instead of staging a job's stdout/stderr in a temp file on disk (see `run_on_disk` in
+//! `super::super::jobs`), a job
+//! spawned with `Stdio::piped()` has its output drained directly into memory by
+//! `StreamForwarder`. The pipe file descriptors are set non-blocking so draining never stalls
+//! the rest of the pipeline waiting on a child that has gone quiet. Non-blocking pipe draining
+//! is a Unix-only trick (see `super::super::super::arguments::rlimit` for the same split), so
+//! non-Unix platforms get a stub that reports `--pipe` as unsupported instead of forwarding.
+
+#[cfg(unix)]
+mod unix {
+    use std::io::{self, Read};
+    use std::os::unix::io::AsRawFd;
+    use std::process::{ChildStderr, ChildStdout};
+
+    use libc::{self, F_GETFL, F_SETFL, O_NONBLOCK};
+
+    /// Accumulates a single job's stdout and stderr entirely in memory, standing in for the
+    /// stdout/stderr temp files used by the disk-backed path.
+    pub struct StreamForwarder {
+        stdout: ChildStdout,
+        stderr: ChildStderr,
+        pub stdout_buffer: Vec<u8>,
+        pub stderr_buffer: Vec<u8>
+    }
+
+    impl StreamForwarder {
+        /// Wraps a child's pipes, setting both file descriptors non-blocking so `drain` never
+        /// blocks waiting on a child that hasn't produced output yet.
+        pub fn new(stdout: ChildStdout, stderr: ChildStderr) -> io::Result<StreamForwarder> {
+            try!(set_nonblocking(stdout.as_raw_fd()));
+            try!(set_nonblocking(stderr.as_raw_fd()));
+
+            Ok(StreamForwarder {
+                stdout: stdout,
+                stderr: stderr,
+                stdout_buffer: Vec::new(),
+                stderr_buffer: Vec::new()
+            })
+        }
+
+        /// Reads whatever is currently available on stdout and stderr without blocking, appending
+        /// it to the accumulators. Returns `true` if either stream produced data.
+        pub fn drain(&mut self) -> bool {
+            let stdout_read = drain_into(&mut self.stdout, &mut self.stdout_buffer);
+            let stderr_read = drain_into(&mut self.stderr, &mut self.stderr_buffer);
+            stdout_read || stderr_read
+        }
+    }
+
+    /// Reads everything currently buffered on `source` into `sink`, treating `WouldBlock` as "no
+    /// data yet" rather than an error.
+    pub fn drain_into<R: Read>(source: &mut R, sink: &mut Vec<u8>) -> bool {
+        let mut chunk = [0u8; 8192];
+        let mut read_any = false;
+
+        loop {
+            match source.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(bytes_read) => {
+                    sink.extend_from_slice(&chunk[0..bytes_read]);
+                    read_any = true;
+                },
+                Err(ref why) if why.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => break
+            }
+        }
+
+        read_any
+    }
+
+    /// Sets a raw file descriptor to non-blocking mode via `fcntl`.
+    fn set_nonblocking(fd: i32) -> io::Result<()> {
+        unsafe {
+            let flags = libc::fcntl(fd, F_GETFL);
+            if flags < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if libc::fcntl(fd, F_SETFL, flags | O_NONBLOCK) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::drain_into;
+        use std::io::{self, Read};
+
+        /// A `Read` that yields `chunks` in order, then reports `WouldBlock` forever.
+        struct ScriptedReader {
+            chunks: Vec<Vec<u8>>
+        }
+
+        impl Read for ScriptedReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.chunks.is_empty() {
+                    return Err(io::Error::new(io::ErrorKind::WouldBlock, "no data"));
+                }
+
+                let chunk = self.chunks.remove(0);
+                buf[0..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+        }
+
+        #[test]
+        fn drain_into_accumulates_until_would_block() {
+            let mut reader = ScriptedReader { chunks: vec![b"foo".to_vec(), b"bar".to_vec()] };
+            let mut sink = Vec::new();
+
+            let read_any = drain_into(&mut reader, &mut sink);
+
+            assert!(read_any);
+            assert_eq!(sink, b"foobar".to_vec());
+        }
+
+        #[test]
+        fn drain_into_reports_no_data_on_immediate_would_block() {
+            let mut reader = ScriptedReader { chunks: Vec::new() };
+            let mut sink = Vec::new();
+
+            let read_any = drain_into(&mut reader, &mut sink);
+
+            assert!(!read_any);
+            assert!(sink.is_empty());
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use self::unix::StreamForwarder;
+
+/// Non-Unix stub: `--pipe` relies on setting pipe file descriptors non-blocking via `fcntl`,
+/// which has no equivalent here, so construction always fails with a clear error instead of
+/// silently blocking the pipeline.
+#[cfg(not(unix))]
+mod other {
+    use std::io;
+    use std::process::{ChildStderr, ChildStdout};
+
+    pub struct StreamForwarder {
+        pub stdout_buffer: Vec<u8>,
+        pub stderr_buffer: Vec<u8>
+    }
+
+    impl StreamForwarder {
+        pub fn new(_stdout: ChildStdout, _stderr: ChildStderr) -> io::Result<StreamForwarder> {
+            Err(io::Error::new(io::ErrorKind::Other, "--pipe is not supported on this platform"))
+        }
+
+        pub fn drain(&mut self) -> bool {
+            false
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub use self::other::StreamForwarder;
@@ -1,16 +1,63 @@
 use std::fs::{self, File};
 use std::io::{self, Write, Read};
 use std::path::Path;
-use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::Duration;
 
+use crossbeam_channel::{Receiver, RecvTimeoutError};
 use disk_buffer::DiskBuffer;
 use filepaths;
 use arguments::Args;
 use super::pipe::disk::State;
 use smallvec::SmallVec;
 
+/// How long the ordering thread waits for the next message before performing a tail-read pass
+/// over the job that is currently allowed to print.
+const TAIL_INTERVAL: Duration = Duration::from_millis(15);
+
+/// Default high-water mark for the out-of-order completion buffer, overridable with
+/// `--buffer-limit`. Matches the inline capacity of the `buffer` `SmallVec` so the common case
+/// never spills to the heap.
+const DEFAULT_MAX_BUFFER_LENGTH: usize = 32;
+
+/// Dispatching resumes once the buffer has drained back down to half the high-water mark,
+/// rather than the instant it dips below it, so throttling doesn't oscillate at the threshold.
+const LOW_WATER_DIVISOR: usize = 2;
+
+/// Shared flag the job dispatcher polls to know whether it should pause launching new jobs
+/// because the out-of-order completion buffer has grown past its high-water mark.
+pub struct Backpressure(AtomicBool);
+
+impl Backpressure {
+    pub fn new() -> Arc<Backpressure> {
+        Arc::new(Backpressure(AtomicBool::new(false)))
+    }
+
+    /// Returns `true` from the moment the buffer grows past its high-water mark until it has
+    /// drained back down to its low-water mark; meanwhile the dispatcher should hold off
+    /// launching further jobs.
+    pub fn is_throttled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn set(&self, throttled: bool) {
+        self.0.store(throttled, Ordering::Release);
+    }
+}
+
+/// The two behaviors the ordering thread switches between while the job equal to `counter`
+/// hasn't completed yet.
+enum ReceiverMode {
+    /// The default ordered path: completions that arrive ahead of `counter` are held in
+    /// `buffer` until their turn comes up.
+    Buffering,
+    /// Entered while the job equal to `counter` is still running, so its partial output is
+    /// forwarded live via `read_outputs!` instead of waiting idle for it to finish.
+    Streaming
+}
+
 /// Reads the standard output and error files of the current unit, writing them to the standard output/error.
 macro_rules! read_outputs {
     ($stdout:ident, $stderr:ident, $buffer:ident, $stdout_out:ident, $stderr_out:ident) => {
@@ -58,6 +105,31 @@ macro_rules! open_job_files {
     }}
 }
 
+/// Writes a `--pipe` job's already-collected stdout/stderr buffers to the real standard
+/// output/error, the diskless counterpart of `read_outputs!`.
+macro_rules! write_piped_output {
+    ($stdout_buf:ident, $stderr_buf:ident, $stdout_out:ident, $stderr_out:ident) => {
+        if let Err(why) = $stdout_out.write($stdout_buf.as_slice()) {
+            let _ = write!($stderr_out, "parallel: I/O error: unable to write to standard output: {}\n", why);
+        }
+
+        if let Err(why) = $stderr_out.write($stderr_buf.as_slice()) {
+            let _ = write!($stderr_out, "parallel: I/O error: unable to write to standard error: {}\n", why);
+        }
+    }
+}
+
+/// Buffers an out-of-order completion and throttles the dispatcher once the buffer has grown
+/// past its high-water mark.
+macro_rules! buffer_and_throttle {
+    ($buffer:ident, $state:expr, $max_buffer_length:ident, $backpressure:ident) => {{
+        $buffer.push($state);
+        if $buffer.len() > $max_buffer_length {
+            $backpressure.set(true);
+        }
+    }}
+}
+
 /// Append the current job to the processed file
 macro_rules! append_to_processed {
     ($processed:ident, $input:ident, $stderr:ident) => {{
@@ -69,10 +141,15 @@ macro_rules! append_to_processed {
 
 #[allow(cyclomatic_complexity)]
 /// Tail and print the standard output and error of each process in the correct order
-pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &Path, errors_path: &Path) {
+pub fn receive_messages(input_rx: Receiver<State>, args: Arc<Args>, backpressure: Arc<Backpressure>, processed_path: &Path, errors_path: &Path) {
     let stdout = io::stdout();
     let stderr = io::stderr();
 
+    // The buffer's high-water mark, overridable with `--buffer-limit`, and the low-water mark
+    // dispatching resumes at once the buffer has drained back down.
+    let max_buffer_length = args.buffer_limit.unwrap_or(DEFAULT_MAX_BUFFER_LENGTH);
+    let low_water_mark = low_water_mark(max_buffer_length);
+
     // Keeps track of which job is currently allowed to print to standard output/error.
     let mut counter = 0;
     // The following `buffer` is used to store completed jobs that are awaiting processing.
@@ -91,8 +168,8 @@ pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &
     let (truncate_size, mut stdout_path, mut stderr_path) = filepaths::new_job(counter);
 
     // The loop will only quit once all inputs have been processed
-    while counter < args.ninputs {
-        let mut tail_next = false;
+    'outer: while counter < args.ninputs {
+        let mut mode = ReceiverMode::Buffering;
 
         match input_rx.recv().unwrap() {
             State::Completed(id, name) => {
@@ -106,8 +183,8 @@ pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &
                     remove_job_files!(stdout_path, stderr_path, stderr);
                     counter += 1;
                 } else {
-                    buffer.push(State::Completed(id, name));
-                    tail_next = true;
+                    buffer_and_throttle!(buffer, State::Completed(id, name), max_buffer_length, backpressure);
+                    mode = ReceiverMode::Streaming;
                 }
             },
             State::Error(id, message) => {
@@ -118,17 +195,28 @@ pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &
                         let _ = write!(stderr, "parallel: I/O error: {}", why);
                     }
                 } else {
-                    buffer.push(State::Error(id, message));
+                    buffer_and_throttle!(buffer, State::Error(id, message), max_buffer_length, backpressure);
+                }
+            },
+            State::Piped(id, name, stdout_buf, stderr_buf) => {
+                if id == counter {
+                    let mut stdout = stdout.lock();
+                    let mut stderr = stderr.lock();
+                    append_to_processed!(processed_file, name, stderr);
+                    write_piped_output!(stdout_buf, stderr_buf, stdout, stderr);
+                    counter += 1;
+                } else {
+                    buffer_and_throttle!(buffer, State::Piped(id, name, stdout_buf, stderr_buf), max_buffer_length, backpressure);
                 }
             }
         }
 
-        if tail_next {
+        if let ReceiverMode::Streaming = mode {
             filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
             let (mut stdout_file, mut stderr_file) = open_job_files!(stdout_path, stderr_path);
 
             loop {
-                match input_rx.try_recv() {
+                match input_rx.recv_timeout(TAIL_INTERVAL) {
                     Ok(State::Completed(id, name)) => {
                         if id == counter {
                             let mut stdout = stdout.lock();
@@ -139,7 +227,7 @@ pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &
                             counter += 1;
                             break
                         } else {
-                            buffer.push(State::Completed(id, name));
+                            buffer_and_throttle!(buffer, State::Completed(id, name), max_buffer_length, backpressure);
                         }
                     },
                     Ok(State::Error(id, message)) => {
@@ -150,10 +238,22 @@ pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &
                                 let _ = write!(stderr, "parallel: I/O error: {}", why);
                             }
                         } else {
-                            buffer.push(State::Error(id, message));
+                            buffer_and_throttle!(buffer, State::Error(id, message), max_buffer_length, backpressure);
                         }
                     },
-                    _ => {
+                    Ok(State::Piped(id, name, stdout_buf, stderr_buf)) => {
+                        if id == counter {
+                            let mut stdout = stdout.lock();
+                            let mut stderr = stderr.lock();
+                            append_to_processed!(processed_file, name, stderr);
+                            write_piped_output!(stdout_buf, stderr_buf, stdout, stderr);
+                            counter += 1;
+                            break
+                        } else {
+                            buffer_and_throttle!(buffer, State::Piped(id, name, stdout_buf, stderr_buf), max_buffer_length, backpressure);
+                        }
+                    },
+                    Err(RecvTimeoutError::Timeout) => {
                         let mut stdout = stdout.lock();
                         let mut stderr = stderr.lock();
                         let mut bytes_read = stdout_file.read(&mut read_buffer).unwrap();
@@ -161,8 +261,8 @@ pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &
 
                         bytes_read = stderr_file.read(&mut read_buffer).unwrap();
                         if bytes_read != 0 { stderr.write(&read_buffer[0..bytes_read]).unwrap(); }
-                        thread::sleep(Duration::from_millis(1));
-                    }
+                    },
+                    Err(RecvTimeoutError::Disconnected) => break 'outer
                 }
             }
         }
@@ -191,12 +291,25 @@ pub fn receive_messages(input_rx: Receiver<State>, args: Args, processed_path: &
                             let _ = write!(stderr, "parallel: I/O error: {}", why);
                         }
                     },
+                    State::Piped(id, ref name, ref stdout_buf, ref stderr_buf) if id == counter => {
+                        let mut stdout = stdout.lock();
+                        let mut stderr = stderr.lock();
+                        append_to_processed!(processed_file, name, stderr);
+                        write_piped_output!(stdout_buf, stderr_buf, stdout, stderr);
+                        counter += 1;
+                        changed = true;
+                        drop.push(index);
+                    },
                     _ => ()
                 }
             }
         }
 
         drop_used_values(&mut buffer, &mut drop);
+
+        if backpressure.is_throttled() && buffer.len() <= low_water_mark {
+            backpressure.set(false);
+        }
     }
 
     if let Err(why) = processed_file.flush() {
@@ -215,4 +328,46 @@ fn drop_used_values(buffer: &mut SmallVec<[State; 32]>, drop: &mut SmallVec<[usi
     for id in drop.drain().rev() {
         let _ = buffer.remove(id);
     }
+}
+
+/// The buffer length dispatching resumes at once throttled, half the high-water mark.
+fn low_water_mark(max_buffer_length: usize) -> usize {
+    max_buffer_length / LOW_WATER_DIVISOR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{low_water_mark, Backpressure};
+    use crossbeam_channel::{self, RecvTimeoutError};
+    use std::time::Duration;
+
+    #[test]
+    fn low_water_mark_is_half_the_high_water_mark() {
+        assert_eq!(low_water_mark(32), 16);
+    }
+
+    #[test]
+    fn low_water_mark_rounds_down_for_odd_limits() {
+        assert_eq!(low_water_mark(33), 16);
+    }
+
+    #[test]
+    fn backpressure_starts_clear_and_can_be_throttled() {
+        let backpressure = Backpressure::new();
+        assert!(!backpressure.is_throttled());
+        backpressure.set(true);
+        assert!(backpressure.is_throttled());
+        backpressure.set(false);
+        assert!(!backpressure.is_throttled());
+    }
+
+    #[test]
+    fn recv_timeout_reports_disconnected_once_every_sender_is_dropped() {
+        let (tx, rx) = crossbeam_channel::unbounded::<()>();
+        drop(tx);
+        match rx.recv_timeout(Duration::from_millis(10)) {
+            Err(RecvTimeoutError::Disconnected) => (),
+            other => panic!("expected Disconnected, got {:?}", other)
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,43 @@
+extern crate crossbeam_channel;
+extern crate disk_buffer;
+extern crate filepaths;
+extern crate libc;
+extern crate num_cpus;
+extern crate permutate;
+extern crate smallvec;
+extern crate tokenizer;
+
+mod arguments;
+mod execute;
+
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use arguments::Args;
+use execute::jobs;
+use execute::receive::{self, Backpressure};
+
+fn main() {
+    let mut args = Args::new();
+    if let Err(why) = args.parse() {
+        why.handle(io::stdout(), io::stderr());
+    }
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let backpressure = Backpressure::new();
+    let args = Arc::new(args);
+
+    let dispatch_args = args.clone();
+    let dispatch_backpressure = backpressure.clone();
+    let dispatcher = thread::spawn(move || {
+        jobs::dispatch(&dispatch_args, tx, dispatch_backpressure);
+    });
+
+    let processed_path = Path::new(".parallel_processed");
+    let errors_path = Path::new(".parallel_errors");
+    receive::receive_messages(rx, args, backpressure, processed_path, errors_path);
+
+    let _ = dispatcher.join();
+}